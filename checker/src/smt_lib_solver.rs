@@ -0,0 +1,667 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// This source code is licensed under the MIT license found in the
+// LICENSE file in the root directory of this source tree.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::rc::Rc;
+
+use mirai_annotations::precondition;
+
+use crate::constant_domain::ConstantDomain;
+use crate::expression::{Expression, ExpressionType};
+use crate::path::Path;
+use crate::smt_solver::{Combined, SmtParam, SmtParamValue, SmtResult, SmtSolver};
+
+/// An `SmtParam` produced by parsing a `(define-fun name () Sort value)` form out of the
+/// model returned by `(get-model)`.
+struct SmtLibParam {
+    name: String,
+    path: Option<Rc<Path>>,
+    val: SmtParamValue,
+}
+
+impl SmtParam for SmtLibParam {
+    fn get_debug_name(&self, debug_map: &HashMap<usize, Rc<String>>) -> String {
+        self.path
+            .as_ref()
+            .and_then(|path| debug_map.get(&path.get_ordinal()))
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| self.name.clone())
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_expr(&self) -> Option<Combined> {
+        self.path.as_ref().map(Combined::from)
+    }
+
+    fn get_path(&self) -> Option<Rc<Path>> {
+        self.path.clone()
+    }
+
+    fn get_initializer(&self, _debug_map: &HashMap<usize, Rc<String>>) -> Option<String> {
+        Some(self.val.to_string())
+    }
+
+    fn get_val(&self) -> SmtParamValue {
+        self.val.clone()
+    }
+
+    fn get_debug_string(&self) -> &str {
+        &self.name
+    }
+}
+
+/// An `SmtSolver<String>` that speaks the standard SMT-LIB 2 text protocol to an external
+/// solver process (z3, cvc5, ...) over its stdin/stdout. This lets a user verify with
+/// whichever SMT-LIB 2 compliant solver they have installed, rather than being tied to a
+/// solver that MIRAI links against natively.
+pub struct SmtLibSolver {
+    stdin: RefCell<ChildStdin>,
+    stdout: RefCell<BufReader<ChildStdout>>,
+    // Keeping the child around for its entire lifetime so that its stdin/stdout pipes
+    // stay open for the lifetime of the solver.
+    _child: Child,
+    // Maps each SMT-LIB variable name introduced while translating an expression back to
+    // the MIRAI path it came from, so that `get_model_params` can report results by path.
+    names_to_paths: RefCell<HashMap<String, Rc<Path>>>,
+    number_of_backtracks: RefCell<u32>,
+    // Counter used to name fresh opaque constants for `Expression` variants `translate` does
+    // not otherwise understand, so that each one gets a distinct, valid SMT-LIB symbol.
+    next_uninterpreted_id: RefCell<u32>,
+}
+
+impl SmtLibSolver {
+    /// Spawns `solver_path` (e.g. "z3", "cvc5") in interactive mode and wires up its pipes.
+    pub fn new(solver_path: &str) -> Self {
+        let mut child = Command::new(solver_path)
+            .arg("-in")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap_or_else(|e| panic!("could not start smt solver {}: {}", solver_path, e));
+        let stdin = child.stdin.take().expect("solver did not provide a stdin pipe");
+        let stdout = child.stdout.take().expect("solver did not provide a stdout pipe");
+        let solver = SmtLibSolver {
+            stdin: RefCell::new(stdin),
+            stdout: RefCell::new(BufReader::new(stdout)),
+            _child: child,
+            names_to_paths: RefCell::new(HashMap::new()),
+            number_of_backtracks: RefCell::new(0),
+            next_uninterpreted_id: RefCell::new(0),
+        };
+        solver.send_command("(set-option :produce-models true)");
+        solver.send_command("(set-option :produce-unsat-cores true)");
+        solver
+    }
+
+    fn send_command(&self, command: &str) {
+        let mut stdin = self.stdin.borrow_mut();
+        writeln!(stdin, "{}", command).expect("failed to write to smt solver");
+        stdin.flush().expect("failed to flush smt solver stdin");
+    }
+
+    /// Reads back a single reply (a bare atom such as `sat`, or a parenthesized s-expression
+    /// such as the output of `(get-model)`) from the solver.
+    fn read_reply(&self) -> String {
+        let mut stdout = self.stdout.borrow_mut();
+        let mut reply = String::new();
+        let mut depth: i32 = 0;
+        let mut seen_open_paren = false;
+        loop {
+            let mut line = String::new();
+            if stdout.read_line(&mut line).unwrap_or(0) == 0 {
+                break;
+            }
+            for ch in line.chars() {
+                match ch {
+                    '(' => {
+                        depth += 1;
+                        seen_open_paren = true;
+                    }
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+            }
+            reply.push_str(&line);
+            if seen_open_paren && depth <= 0 {
+                break;
+            }
+            if !seen_open_paren && !line.trim().is_empty() {
+                break;
+            }
+        }
+        reply.trim().to_string()
+    }
+
+    fn sort_name(var_type: &ExpressionType) -> String {
+        match var_type {
+            ExpressionType::Bool => "Bool".to_string(),
+            // The IEEE 754 binary32/binary64 sorts, per the SMT-LIB FloatingPoint theory
+            // (exponent width, significand width including the implicit leading bit).
+            ExpressionType::F32 => "(_ FloatingPoint 8 24)".to_string(),
+            ExpressionType::F64 => "(_ FloatingPoint 11 53)".to_string(),
+            _ => "Int".to_string(),
+        }
+    }
+
+    /// Declares `path` as an SMT-LIB constant, returning the name it was declared under.
+    fn declare_variable(&self, path: &Rc<Path>, var_type: &ExpressionType) -> String {
+        let name = format!("path!{}", path.get_ordinal());
+        if !self.names_to_paths.borrow().contains_key(&name) {
+            self.send_command(&format!("(declare-const {} {})", name, Self::sort_name(var_type)));
+            self.names_to_paths
+                .borrow_mut()
+                .insert(name.clone(), path.clone());
+        }
+        name
+    }
+
+    /// Renders `mirai_expression` as an SMT-LIB 2 s-expression, declaring any free variables
+    /// it references along the way.
+    fn translate(&self, mirai_expression: &Expression) -> String {
+        match mirai_expression {
+            Expression::Top => "true".to_string(),
+            Expression::Bottom => "false".to_string(),
+            Expression::CompileTimeConstant { value } => self.translate_constant(value),
+            Expression::Variable { path, var_type } => self.declare_variable(path, var_type),
+            Expression::Add { left, right } => self.binary("+", left, right),
+            Expression::Sub { left, right } => self.binary("-", left, right),
+            Expression::Mul { left, right } => self.binary("*", left, right),
+            Expression::Div { left, right } => self.binary("div", left, right),
+            Expression::Rem { left, right } => self.binary("mod", left, right),
+            Expression::And { left, right } => self.binary("and", left, right),
+            Expression::Or { left, right } => self.binary("or", left, right),
+            Expression::Equals { left, right } => self.binary("=", left, right),
+            Expression::Ne { left, right } => format!("(not {})", self.binary("=", left, right)),
+            Expression::GreaterThan { left, right } => self.binary(">", left, right),
+            Expression::GreaterOrEqual { left, right } => self.binary(">=", left, right),
+            Expression::LessThan { left, right } => self.binary("<", left, right),
+            Expression::LessOrEqual { left, right } => self.binary("<=", left, right),
+            Expression::LogicalNot { operand } => format!("(not {})", self.translate(operand)),
+            Expression::Neg { operand } => format!("(- {})", self.translate(operand)),
+            Expression::ConditionalExpression {
+                condition,
+                consequent,
+                alternate,
+            } => format!(
+                "(ite {} {} {})",
+                self.translate(condition),
+                self.translate(consequent),
+                self.translate(alternate)
+            ),
+            // Anything not explicitly modeled above is declared as a fresh, otherwise
+            // unconstrained opaque constant, which keeps the predicate well formed without
+            // pretending to understand semantics the translator does not yet implement. The
+            // expression's debug form is sent only as a trailing `;` comment on the constant's
+            // declaration, never spliced into the predicate itself.
+            _ => self.declare_opaque(mirai_expression),
+        }
+    }
+
+    /// Renders a `ConstantDomain` as a literal SMT-LIB token. Going through `Display`/`Debug`
+    /// directly (as `Expression::CompileTimeConstant` used to) splices arbitrary text into the
+    /// command stream -- in particular, a negative integer constant renders via `Display` as
+    /// the bare token `-5`, which is not a legal SMT-LIB numeral (the grammar requires the
+    /// sub-form `(- 5)`, as `parse_model`'s `Int` handling already has to account for on the
+    /// read side). Anything this does not recognize falls back to `declare_opaque`, the same
+    /// escape hatch `translate` uses for `Expression` variants it cannot render faithfully.
+    fn translate_constant(&self, value: &ConstantDomain) -> String {
+        match value {
+            ConstantDomain::True => "true".to_string(),
+            ConstantDomain::False => "false".to_string(),
+            ConstantDomain::I128(n) => Self::signed_numeral(*n),
+            ConstantDomain::U128(n) => n.to_string(),
+            _ => self.declare_opaque(value),
+        }
+    }
+
+    /// Renders an integer as an SMT-LIB numeral, using the `(- N)` sub-form the grammar
+    /// requires for negative values rather than a bare `-N` token.
+    fn signed_numeral(val: i128) -> String {
+        if val < 0 {
+            format!("(- {})", val.unsigned_abs())
+        } else {
+            val.to_string()
+        }
+    }
+
+    /// Declares a fresh, unconstrained constant standing in for a MIRAI value (an `Expression`
+    /// or `ConstantDomain`) this translator does not (yet) know how to render faithfully.
+    fn declare_opaque(&self, mirai_value: &dyn std::fmt::Debug) -> String {
+        let mut next_id = self.next_uninterpreted_id.borrow_mut();
+        let name = format!("uninterpreted!{}", *next_id);
+        *next_id += 1;
+        drop(next_id);
+        // `;` starts a line comment in SMT-LIB 2, so the debug form is safe to attach for
+        // troubleshooting as long as it can't itself contain a newline.
+        let origin = format!("{:?}", mirai_value).replace('\n', " ");
+        self.send_command(&format!("(declare-const {} Int) ; {}", name, origin));
+        name
+    }
+
+    fn binary(&self, op: &str, left: &Expression, right: &Expression) -> String {
+        format!("({} {} {})", op, self.translate(left), self.translate(right))
+    }
+
+    /// Parses the `(define-fun name () Sort value)` forms returned by `(get-model)` into
+    /// `SmtParam` instances, wiring each back to the `Path` it was declared for.
+    ///
+    /// `Sort` is not always a single token: `Bool` and `Int` are, but `(_ BitVec 32)`,
+    /// `(_ FloatingPoint 8 24)`, `String` and `(Array Int Int)` are compound sorts (or, for
+    /// `String`, a value that can itself contain whitespace), so this walks `define` as an
+    /// s-expression rather than indexing into a flat token list at fixed offsets.
+    fn parse_model(&self, model: &str) -> Vec<Box<dyn SmtParam>> {
+        let names_to_paths = self.names_to_paths.borrow();
+        let mut params: Vec<Box<dyn SmtParam>> = Vec::new();
+        for define in split_top_level_forms(model) {
+            let tokens = tokenize(&define);
+            let sexp = match parse_sexp(&tokens) {
+                Some((sexp, _)) => sexp,
+                None => continue,
+            };
+            let items = match &sexp {
+                Sexp::List(items) => items,
+                Sexp::Atom(_) => continue,
+            };
+            // ( define-fun <name> ( ) <sort> <value> )
+            let (head, name, sort, value) = match items.as_slice() {
+                [head, Sexp::Atom(name), _args, sort, value] => (head, name, sort, value),
+                _ => continue,
+            };
+            if !matches!(head, Sexp::Atom(op) if op == "define-fun") {
+                continue;
+            }
+            let val = value_for_sort(sort, value, &define);
+            let path = names_to_paths.get(name).cloned();
+            params.push(Box::new(SmtLibParam { name: name.clone(), path, val }));
+        }
+        params
+    }
+}
+
+/// A minimal s-expression tree, just enough structure to tell a compound sort (or value) like
+/// `(_ BitVec 32)` apart from a bare symbol like `Int`.
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+/// Parses one s-expression off the front of `tokens`, returning it along with whatever tokens
+/// remain. `tokens` must already have been produced by `tokenize`, so every paren is its own
+/// token.
+fn parse_sexp(tokens: &[String]) -> Option<(Sexp, &[String])> {
+    let (first, rest) = tokens.split_first()?;
+    if first == "(" {
+        let mut items = Vec::new();
+        let mut remaining = rest;
+        loop {
+            if remaining.first().map(String::as_str) == Some(")") {
+                return Some((Sexp::List(items), &remaining[1..]));
+            }
+            let (item, next_remaining) = parse_sexp(remaining)?;
+            items.push(item);
+            remaining = next_remaining;
+        }
+    } else {
+        Some((Sexp::Atom(first.clone()), rest))
+    }
+}
+
+/// Classifies a parsed `Sort` sexp and, using that classification, interprets `value` (the
+/// model's assignment for a variable of that sort). `define` is the original, untokenized
+/// text of the enclosing `define-fun`, needed only for `String` sorts: `tokenize` splits on
+/// whitespace, which would otherwise break a string value containing spaces back apart.
+fn value_for_sort(sort: &Sexp, value: &Sexp, define: &str) -> SmtParamValue {
+    match sort {
+        Sexp::Atom(name) if name == "Bool" => match value {
+            Sexp::Atom(text) => SmtParamValue::Bool { val: text == "true" },
+            _ => SmtParamValue::Unknown,
+        },
+        Sexp::Atom(name) if name == "Int" => sexp_to_numeral(value),
+        Sexp::Atom(name) if name == "String" => {
+            match (define.find('"'), define.rfind('"')) {
+                (Some(start), Some(end)) if end > start => {
+                    SmtParamValue::Str { val: define[start + 1..end].replace("\"\"", "\"") }
+                }
+                _ => SmtParamValue::Unknown,
+            }
+        }
+        Sexp::List(items) => match items.as_slice() {
+            // (_ BitVec <width>)
+            [Sexp::Atom(u), Sexp::Atom(kind), Sexp::Atom(width)] if u == "_" && kind == "BitVec" => {
+                let width: u32 = width.parse().unwrap_or(0);
+                match sexp_to_bits(value) {
+                    Some(val) => SmtParamValue::BitVec { width, val },
+                    None => SmtParamValue::Unknown,
+                }
+            }
+            // (_ FloatingPoint <exponent-width> <significand-width>)
+            [Sexp::Atom(u), Sexp::Atom(kind), Sexp::Atom(_), Sexp::Atom(sig)] if u == "_" && kind == "FloatingPoint" => {
+                let double = sig.parse::<u32>().unwrap_or(24) > 24;
+                match sexp_to_float_bits(value) {
+                    Some(bits) => SmtParamValue::Float { bits, double },
+                    None => SmtParamValue::Unknown,
+                }
+            }
+            // (Array <domain> <range>)
+            [Sexp::Atom(kind), _domain, _range] if kind == "Array" => sexp_to_array(value),
+            _ => SmtParamValue::Unknown,
+        },
+        _ => SmtParamValue::Unknown,
+    }
+}
+
+/// Parses an `Int`-sorted value, which is either a plain numeral atom or the negative-numeral
+/// sub-form `(- <magnitude>)`.
+fn sexp_to_numeral(value: &Sexp) -> SmtParamValue {
+    match value {
+        Sexp::Atom(text) => text
+            .parse::<i128>()
+            .map(|val| SmtParamValue::Numeral { val })
+            .unwrap_or(SmtParamValue::Unknown),
+        Sexp::List(items) => match items.as_slice() {
+            [Sexp::Atom(op), Sexp::Atom(magnitude)] if op == "-" => magnitude
+                .parse::<i128>()
+                .map(|val| SmtParamValue::Numeral { val: -val })
+                .unwrap_or(SmtParamValue::Unknown),
+            _ => SmtParamValue::Unknown,
+        },
+    }
+}
+
+/// Parses a bitvector literal, in either of the two forms solvers commonly print them in:
+/// `#xHEX` or `#bBINARY`.
+fn sexp_to_bits(value: &Sexp) -> Option<u128> {
+    let text = match value {
+        Sexp::Atom(text) => text.as_str(),
+        Sexp::List(_) => return None,
+    };
+    if let Some(hex) = text.strip_prefix("#x") {
+        u128::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = text.strip_prefix("#b") {
+        u128::from_str_radix(bin, 2).ok()
+    } else {
+        None
+    }
+}
+
+/// Parses a FloatingPoint literal of the form a solver typically emits for `(get-model)`:
+/// `(fp <sign-bitvec> <exponent-bitvec> <significand-bitvec>)`, packing the three fields back
+/// together into their combined bit pattern.
+fn sexp_to_float_bits(value: &Sexp) -> Option<u64> {
+    let items = match value {
+        Sexp::List(items) => items,
+        Sexp::Atom(_) => return None,
+    };
+    let (op, sign, exponent, significand) = match items.as_slice() {
+        [op, sign, exponent, significand] => (op, sign, exponent, significand),
+        _ => return None,
+    };
+    if !matches!(op, Sexp::Atom(text) if text == "fp") {
+        return None;
+    }
+    let sign = sexp_to_bits(sign)?;
+    let exponent_width = bitvec_width(exponent)?;
+    let exponent = sexp_to_bits(exponent)?;
+    let significand_width = bitvec_width(significand)?;
+    let significand = sexp_to_bits(significand)?;
+    Some((sign << (exponent_width + significand_width)) | (exponent << significand_width) | significand)
+}
+
+/// The number of bits printed in a `#b...`/`#x...` bitvector literal.
+fn bitvec_width(value: &Sexp) -> Option<u32> {
+    let text = match value {
+        Sexp::Atom(text) => text.as_str(),
+        Sexp::List(_) => return None,
+    };
+    if let Some(bin) = text.strip_prefix("#b") {
+        Some(bin.len() as u32)
+    } else {
+        text.strip_prefix("#x").map(|hex| (hex.len() * 4) as u32)
+    }
+}
+
+/// Parses an `Array`-sorted value: either a constant array `((as const (Array K V)) default)`,
+/// or a chain of updates `(store <base> <key> <value>)` built on top of one. Anything else
+/// (for example a solver's internal `(_ as-array k!0)` reference to a separately defined
+/// function) is not something this translator constructed an `Array` expression for in the
+/// first place, so it is reported as `Unknown` rather than guessed at.
+fn sexp_to_array(value: &Sexp) -> SmtParamValue {
+    let items = match value {
+        Sexp::List(items) => items,
+        Sexp::Atom(_) => return SmtParamValue::Unknown,
+    };
+    match items.as_slice() {
+        [Sexp::Atom(op), base, key, entry_value] if op == "store" => {
+            match sexp_to_array(base) {
+                SmtParamValue::Array { mut entries, default } => {
+                    entries.push((sexp_to_numeral(key), sexp_to_numeral(entry_value)));
+                    SmtParamValue::Array { entries, default }
+                }
+                _ => SmtParamValue::Unknown,
+            }
+        }
+        [Sexp::List(head), default] => match head.as_slice() {
+            [Sexp::Atom(op), Sexp::Atom(kind), ..] if op == "as" && kind == "const" => {
+                SmtParamValue::Array { entries: Vec::new(), default: Box::new(sexp_to_numeral(default)) }
+            }
+            _ => SmtParamValue::Unknown,
+        },
+        _ => SmtParamValue::Unknown,
+    }
+}
+
+/// Splits a `(get-model)` response into its top level `(define-fun ...)` forms.
+fn split_top_level_forms(text: &str) -> Vec<String> {
+    let mut forms = Vec::new();
+    let mut depth = 0i32;
+    let mut current = String::new();
+    let mut started = false;
+    for ch in text.chars() {
+        if ch == '(' {
+            if depth == 1 {
+                started = true;
+            }
+            depth += 1;
+        }
+        if depth >= 2 || (depth == 1 && started) {
+            current.push(ch);
+        }
+        if ch == ')' {
+            depth -= 1;
+            if depth == 1 && started {
+                forms.push(current.trim().to_string());
+                current.clear();
+                started = false;
+            }
+        }
+    }
+    forms
+}
+
+fn tokenize(s: &str) -> Vec<String> {
+    s.replace('(', " ( ")
+        .replace(')', " ) ")
+        .split_whitespace()
+        .map(|t| t.to_string())
+        .collect()
+}
+
+impl SmtSolver<String> for SmtLibSolver {
+    fn as_debug_string(&self, expression: &String) -> String {
+        expression.clone()
+    }
+
+    fn assert(&self, expression: &String) {
+        self.send_command(&format!("(assert {})", expression));
+    }
+
+    fn assert_named(&self, expression: &String, label: &str) {
+        self.send_command(&format!("(assert (! {} :named {}))", expression, label));
+    }
+
+    fn reset(&self) {
+        self.send_command("(reset)");
+        self.names_to_paths.borrow_mut().clear();
+        *self.number_of_backtracks.borrow_mut() = 0;
+        *self.next_uninterpreted_id.borrow_mut() = 0;
+    }
+
+    fn backtrack(&self) {
+        precondition!(*self.number_of_backtracks.borrow() > 0);
+        self.send_command("(pop 1)");
+        *self.number_of_backtracks.borrow_mut() -= 1;
+    }
+
+    fn get_as_smt_predicate(&self, mirai_expression: &Expression) -> String {
+        self.translate(mirai_expression)
+    }
+
+    fn get_model_as_string(&self) -> String {
+        self.send_command("(get-model)");
+        self.read_reply()
+    }
+
+    fn get_model_params(&self, _mirai_expr: &Expression) -> Vec<Box<dyn SmtParam>> {
+        let model = self.get_model_as_string();
+        self.parse_model(&model)
+    }
+
+    fn get_solver_state_as_string(&self) -> String {
+        self.send_command("(get-assertions)");
+        self.read_reply()
+    }
+
+    fn get_unsat_core(&self) -> Vec<String> {
+        self.send_command("(get-unsat-core)");
+        tokenize(&self.read_reply())
+            .into_iter()
+            .filter(|token| token != "(" && token != ")")
+            .collect()
+    }
+
+    fn invert_predicate(&self, expression: &String) -> String {
+        format!("(not {})", expression)
+    }
+
+    fn set_backtrack_position(&self) {
+        precondition!(*self.number_of_backtracks.borrow() < 1000);
+        self.send_command("(push 1)");
+        *self.number_of_backtracks.borrow_mut() += 1;
+    }
+
+    fn solve(&self) -> SmtResult {
+        self.send_command("(check-sat)");
+        match self.read_reply().as_str() {
+            "sat" => SmtResult::Satisfiable,
+            "unsat" => SmtResult::Unsatisfiable,
+            _ => SmtResult::Undefined,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_splits_parens_into_their_own_tokens() {
+        assert_eq!(
+            tokenize("(define-fun x () Int (- 5))"),
+            vec!["(", "define-fun", "x", "(", ")", "Int", "(", "-", "5", ")", ")"],
+        );
+    }
+
+    #[test]
+    fn split_top_level_forms_separates_sibling_defines() {
+        let model = "(model (define-fun x () Int 1) (define-fun y () Bool true))";
+        assert_eq!(
+            split_top_level_forms(model),
+            vec!["(define-fun x () Int 1)", "(define-fun y () Bool true)"],
+        );
+    }
+
+    #[test]
+    fn parse_sexp_reads_one_compound_sort_and_leaves_the_rest() {
+        let tokens = tokenize("(_ BitVec 32) Bool");
+        let (sexp, rest) = parse_sexp(&tokens).unwrap();
+        match sexp {
+            Sexp::List(items) => assert_eq!(items.len(), 3),
+            Sexp::Atom(_) => panic!("expected a list"),
+        }
+        assert_eq!(rest, ["Bool"]);
+    }
+
+    #[test]
+    fn sexp_to_numeral_parses_plain_and_negative_forms() {
+        let plain = tokenize("5");
+        let (sexp, _) = parse_sexp(&plain).unwrap();
+        assert_eq!(sexp_to_numeral(&sexp), SmtParamValue::Numeral { val: 5 });
+
+        let negative = tokenize("(- 5)");
+        let (sexp, _) = parse_sexp(&negative).unwrap();
+        assert_eq!(sexp_to_numeral(&sexp), SmtParamValue::Numeral { val: -5 });
+    }
+
+    #[test]
+    fn sexp_to_bits_parses_hex_and_binary_literals() {
+        let hex = tokenize("#x2a");
+        let (sexp, _) = parse_sexp(&hex).unwrap();
+        assert_eq!(sexp_to_bits(&sexp), Some(0x2a));
+
+        let bin = tokenize("#b101");
+        let (sexp, _) = parse_sexp(&bin).unwrap();
+        assert_eq!(sexp_to_bits(&sexp), Some(0b101));
+    }
+
+    #[test]
+    fn sexp_to_float_bits_packs_sign_exponent_and_significand() {
+        // (fp #b0 #b0 #b00): sign 0, a one-bit exponent of 0, a two-bit significand of 0.
+        let tokens = tokenize("(fp #b0 #b0 #b00)");
+        let (sexp, _) = parse_sexp(&tokens).unwrap();
+        assert_eq!(sexp_to_float_bits(&sexp), Some(0));
+
+        // sign bit set, shifted above a 1-bit exponent and 2-bit significand.
+        let tokens = tokenize("(fp #b1 #b0 #b00)");
+        let (sexp, _) = parse_sexp(&tokens).unwrap();
+        assert_eq!(sexp_to_float_bits(&sexp), Some(0b1_0_00));
+    }
+
+    #[test]
+    fn value_for_sort_parses_bitvec_and_array_sorts() {
+        let sort_tokens = tokenize("(_ BitVec 8)");
+        let (sort, _) = parse_sexp(&sort_tokens).unwrap();
+        let value_tokens = tokenize("#xff");
+        let (value, _) = parse_sexp(&value_tokens).unwrap();
+        assert_eq!(
+            value_for_sort(&sort, &value, ""),
+            SmtParamValue::BitVec { width: 8, val: 0xff },
+        );
+
+        let sort_tokens = tokenize("(Array Int Int)");
+        let (sort, _) = parse_sexp(&sort_tokens).unwrap();
+        let value_tokens = tokenize("(store ((as const (Array Int Int)) 0) 1 9)");
+        let (value, _) = parse_sexp(&value_tokens).unwrap();
+        assert_eq!(
+            value_for_sort(&sort, &value, ""),
+            SmtParamValue::Array {
+                entries: vec![(SmtParamValue::Numeral { val: 1 }, SmtParamValue::Numeral { val: 9 })],
+                default: Box::new(SmtParamValue::Numeral { val: 0 }),
+            },
+        );
+    }
+
+    #[test]
+    fn signed_numeral_uses_the_sub_form_for_negative_values() {
+        assert_eq!(SmtLibSolver::signed_numeral(5), "5");
+        assert_eq!(SmtLibSolver::signed_numeral(-5), "(- 5)");
+    }
+}
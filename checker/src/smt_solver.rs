@@ -32,15 +32,88 @@ pub enum SmtResult {
 pub enum SmtParamValue{
     Bool{val: bool},
     Numeral{val: i128},
+    BitVec{width: u32, val: u128},
+    Float{bits: u64, double: bool},
+    Str{val: String},
+    Array{entries: Vec<(SmtParamValue, SmtParamValue)>, default: Box<SmtParamValue>},
+    /// The discriminant of an enum value, as read from the model via `PathSelector::Discriminant`.
+    /// `TestGen` uses this to pick which variant literal to synthesize for an enum-typed argument.
+    Discriminant{val: i128},
     Unknown
 }
 
+impl SmtParamValue {
+    /// Renders this value as a Rust literal suitable for the given textual type annotation.
+    /// Every variant except `Array` renders identically regardless of `type_name` (and just
+    /// defers to `Display`); `Array` needs to know whether it is filling a `Vec<T>`/`[T; N]`
+    /// or a map-like type, since those use unrelated literal syntax.
+    pub fn render(&self, type_name: &str) -> String {
+        match self {
+            SmtParamValue::Array { entries, default: _ } => {
+                let type_name = type_name.trim();
+                if type_name.starts_with("Vec") || (type_name.starts_with('[') && type_name.ends_with(']')) {
+                    // A `Vec`/array literal has no room for an SMT array's `default` (there is
+                    // no "value for any other index" in a fixed-size Rust collection) and needs
+                    // its entries in index order, with no gaps -- a model that only populated a
+                    // sparse subset of indices, or whose keys are not numerals at all, cannot be
+                    // rendered as one, so that case falls back to a `todo!` stub instead of
+                    // silently emitting values in the wrong slots.
+                    let mut indexed: Vec<(i128, &SmtParamValue)> = entries.iter()
+                        .filter_map(|(key, val)| match key {
+                            SmtParamValue::Numeral { val: index } => Some((*index, val)),
+                            _ => None,
+                        })
+                        .collect();
+                    indexed.sort_by_key(|(index, _)| *index);
+                    let contiguous = indexed.len() == entries.len()
+                        && indexed.iter().enumerate().all(|(i, (index, _))| *index == i as i128);
+                    if contiguous {
+                        let items: Vec<String> = indexed.iter().map(|(_, val)| val.to_string()).collect();
+                        if type_name.starts_with('[') {
+                            format!("[{}]", items.join(", "))
+                        } else {
+                            format!("vec![{}]", items.join(", "))
+                        }
+                    } else {
+                        format!("todo!(\"array model for {} had non-contiguous or non-numeral indices\")", type_name)
+                    }
+                } else {
+                    let inserts: String = entries.iter()
+                        .map(|(key, val)| format!(" m.insert({}, {});", key, val))
+                        .collect();
+                    format!("{{ let mut m = std::collections::HashMap::new();{} m }}", inserts)
+                }
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
 impl std::fmt::Display for SmtParamValue{
 
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(&match self {
             SmtParamValue::Bool { val } => val.to_string(),
             SmtParamValue::Numeral { val } => val.to_string(),
+            SmtParamValue::Discriminant { val } => val.to_string(),
+            // No integer-literal suffix: the generated `let x: T = <value>;` already carries
+            // the declared (possibly signed) type, and a suffixed literal like `0xffu8` would
+            // conflict with it (e.g. `let x: i8 = 0xffu8;` is a type mismatch).
+            SmtParamValue::BitVec { val, .. } => format!("0x{:x}", val),
+            SmtParamValue::Float { bits, double } => {
+                if *double {
+                    format!("f64::from_bits({}u64)", bits)
+                } else {
+                    format!("f32::from_bits({}u32)", *bits as u32)
+                }
+            }
+            SmtParamValue::Str { val } => format!("{:?}.to_string()", val),
+            SmtParamValue::Array { entries, default: _ } => {
+                let inserts: String = entries.iter()
+                    .map(|(key, val)| format!(" m.insert({}, {});", key, val))
+                    .collect();
+                format!("{{ let mut m = std::collections::HashMap::new();{} m }}", inserts)
+            }
             _ => "_".to_string(),
         }, f)
     }
@@ -124,6 +197,19 @@ pub trait SmtSolver<SmtExpressionType> {
     /// Adds the given expression to the current context.
     fn assert(&self, expression: &SmtExpressionType);
 
+    /// Adds the given expression to the current context under `label`, so that it can show up
+    /// in the result of `get_unsat_core` if it takes part in a contradiction. The default
+    /// implementation just asserts the expression without tracking it, which is correct (if
+    /// unhelpful) for solvers that do not support unsat cores.
+    ///
+    /// Labels come only from callers that pass one explicitly, such as the REPL's
+    /// `assert-named <label> <expr>` command. No label is derived from the precondition or
+    /// source-level condition an assertion represents, so `why_unsatisfiable` names only the
+    /// labels that callers chose.
+    fn assert_named(&self, expression: &SmtExpressionType, _label: &str) {
+        self.assert(expression);
+    }
+
     fn reset(&self);
 
     /// Destroy the current context and restore the containing context as current.
@@ -140,6 +226,26 @@ pub trait SmtSolver<SmtExpressionType> {
 
     fn get_model_params(&self, mirai_expr: &Expression) -> Vec<Box<dyn SmtParam>>;
 
+    /// Returns the labels (see `assert_named`) of the minimal subset of named assumptions that
+    /// are together unsatisfiable. Only meaningful to call after `solve` has returned
+    /// `SmtResult::Unsatisfiable`. The default implementation returns an empty core, which is
+    /// correct (if unhelpful) for solvers that do not support unsat-core extraction.
+    fn get_unsat_core(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Explains why the current context is unsatisfiable by naming the mutually contradictory
+    /// assumptions, for use in diagnostics. Only meaningful to call after `solve` has returned
+    /// `SmtResult::Unsatisfiable`.
+    fn why_unsatisfiable(&self) -> String {
+        let core = self.get_unsat_core();
+        if core.is_empty() {
+            "the solver did not report an unsat core".to_string()
+        } else {
+            format!("unsatisfiable because of: {}", core.join(", "))
+        }
+    }
+
     /// Provides a string that contains a listing of all of the definitions and assertions that
     /// have been added to the solver.
     fn get_solver_state_as_string(&self) -> String;
@@ -183,6 +289,8 @@ impl SmtSolver<usize> for SolverStub {
 
     fn assert(&self, _: &usize) {}
 
+    fn assert_named(&self, _: &usize, _: &str) {}
+
     fn reset(&self) {}
 
     fn backtrack(&self) {}
@@ -203,6 +311,10 @@ impl SmtSolver<usize> for SolverStub {
         String::from("not implemented")
     }
 
+    fn get_unsat_core(&self) -> Vec<String> {
+        vec![]
+    }
+
     fn invert_predicate(&self, _: &usize) -> usize {
         0
     }
@@ -213,3 +325,60 @@ impl SmtSolver<usize> for SolverStub {
         SmtResult::Undefined
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numeral(val: i128) -> SmtParamValue {
+        SmtParamValue::Numeral { val }
+    }
+
+    #[test]
+    fn render_vec_orders_entries_by_index() {
+        let value = SmtParamValue::Array {
+            entries: vec![
+                (numeral(1), numeral(20)),
+                (numeral(0), numeral(10)),
+                (numeral(2), numeral(30)),
+            ],
+            default: Box::new(numeral(0)),
+        };
+        assert_eq!(value.render("Vec<i32>"), "vec![10, 20, 30]");
+    }
+
+    #[test]
+    fn render_vec_falls_back_on_non_contiguous_indices() {
+        let value = SmtParamValue::Array {
+            entries: vec![(numeral(0), numeral(10)), (numeral(2), numeral(30))],
+            default: Box::new(numeral(0)),
+        };
+        assert!(value.render("Vec<i32>").starts_with("todo!("));
+    }
+
+    #[test]
+    fn render_vec_falls_back_on_non_numeral_keys() {
+        let value = SmtParamValue::Array {
+            entries: vec![(SmtParamValue::Bool { val: true }, numeral(10))],
+            default: Box::new(numeral(0)),
+        };
+        assert!(value.render("[i32; 1]").starts_with("todo!("));
+    }
+
+    #[test]
+    fn render_map_does_not_annotate_the_default_with_the_container_type() {
+        let value = SmtParamValue::Array {
+            entries: vec![(numeral(1), numeral(10))],
+            default: Box::new(numeral(0)),
+        };
+        let rendered = value.render("HashMap<i32, i32>");
+        assert!(!rendered.contains("_default"));
+        assert!(rendered.contains("m.insert(1, 10)"));
+    }
+
+    #[test]
+    fn bitvec_display_has_no_unsigned_suffix() {
+        let value = SmtParamValue::BitVec { width: 8, val: 0xff };
+        assert_eq!(value.to_string(), "0xff");
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// This source code is licensed under the MIT license found in the
+// LICENSE file in the root directory of this source tree.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::expression::Expression;
+use crate::smt_solver::{SmtResult, SmtSolver};
+
+/// An interactive read-eval-print loop over a live `SmtSolver` context, for stepping into
+/// MIRAI's verification state at an analysis point and exploring why a path condition is (or
+/// isn't) satisfiable. Only ever started when the caller has opted in via a command line flag
+/// (e.g. `--smt-repl`); it is a developer-facing debugging aid, not something that runs by
+/// default.
+pub struct SolverRepl<'a, E, S: SmtSolver<E>> {
+    solver: &'a S,
+    context: &'a Expression,
+    debug_map: &'a HashMap<usize, Rc<String>>,
+    history: Vec<String>,
+    _marker: std::marker::PhantomData<E>,
+}
+
+impl<'a, E, S> SolverRepl<'a, E, S>
+where
+    E: FromStr,
+    E::Err: std::fmt::Debug,
+    S: SmtSolver<E>,
+{
+    pub fn new(
+        solver: &'a S,
+        context: &'a Expression,
+        debug_map: &'a HashMap<usize, Rc<String>>,
+    ) -> Self {
+        SolverRepl {
+            solver,
+            context,
+            debug_map,
+            history: Vec::new(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Runs the REPL against stdin/stdout until the user types `exit`/`quit` or EOF is reached.
+    pub fn run(&mut self) {
+        println!("MIRAI solver repl. Type `help` for a list of commands, `exit` to leave.");
+        let stdin = io::stdin();
+        loop {
+            print!("smt> ");
+            io::stdout().flush().ok();
+            let input = match Self::read_command(&stdin) {
+                Some(input) => input,
+                None => break,
+            };
+            let input = input.trim().to_string();
+            if input.is_empty() {
+                continue;
+            }
+            self.history.push(input.clone());
+            if !self.execute(&input) {
+                break;
+            }
+        }
+    }
+
+    /// Reads one logical command from `stdin`. An `assert` or `assert-named` command accumulates
+    /// further lines until its expression's parentheses balance, so a predicate can be typed
+    /// across several lines; every other command is taken as complete as soon as a line is read.
+    fn read_command(stdin: &io::Stdin) -> Option<String> {
+        let mut buffer = String::new();
+        loop {
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                return if buffer.trim().is_empty() { None } else { Some(buffer) };
+            }
+            buffer.push_str(&line);
+            if !buffer.trim_start().starts_with("assert") || is_balanced(&buffer) {
+                return Some(buffer);
+            }
+            print!("...  ");
+            io::stdout().flush().ok();
+        }
+    }
+
+    fn execute(&mut self, input: &str) -> bool {
+        let mut parts = input.splitn(2, char::is_whitespace);
+        let command = parts.next().unwrap_or("");
+        let rest = parts.next().unwrap_or("").trim();
+        match command {
+            "assert" => match E::from_str(rest) {
+                Ok(expr) => self.solver.assert(&expr),
+                Err(e) => println!("could not parse expression: {:?}", e),
+            },
+            // Lets a user attach their own label to a predicate, since nothing in this crate
+            // derives one from MIRAI's precondition/expression provenance automatically (see
+            // `SmtSolver::assert_named`). Usage: `assert-named <label> <expr>`.
+            "assert-named" => {
+                let mut label_and_expr = rest.splitn(2, char::is_whitespace);
+                match (label_and_expr.next(), label_and_expr.next()) {
+                    (Some(label), Some(expr_text)) if !label.is_empty() => {
+                        match E::from_str(expr_text.trim()) {
+                            Ok(expr) => self.solver.assert_named(&expr, label),
+                            Err(e) => println!("could not parse expression: {:?}", e),
+                        }
+                    }
+                    _ => println!("usage: assert-named <label> <expr>"),
+                }
+            }
+            "unsat-core" => println!("{}", self.solver.why_unsatisfiable()),
+            "check-sat" => println!("{:?}", self.solver.solve()),
+            "model" => {
+                for param in self.solver.get_model_params(self.context) {
+                    println!("{} = {}", param.get_debug_name(self.debug_map), param.get_val());
+                }
+            }
+            "state" => println!("{}", self.solver.get_solver_state_as_string()),
+            "push" => self.solver.set_backtrack_position(),
+            "pop" => self.solver.backtrack(),
+            "history" => {
+                for (i, entry) in self.history.iter().enumerate() {
+                    println!("{}: {}", i, entry);
+                }
+            }
+            "help" => print_help(),
+            "exit" | "quit" => return false,
+            _ => println!("unrecognized command: {}. Type `help` for a list of commands.", command),
+        }
+        true
+    }
+}
+
+/// Starts a `SolverRepl` when `enabled` (wired to MIRAI's `--smt-repl` option), otherwise does
+/// nothing. Kept as a free function so call sites don't need to construct a `SolverRepl` (and
+/// pay for its generic instantiation) when the flag is off.
+pub fn maybe_run_repl<'a, E, S>(
+    enabled: bool,
+    solver: &'a S,
+    context: &'a Expression,
+    debug_map: &'a HashMap<usize, Rc<String>>,
+) where
+    E: FromStr,
+    E::Err: std::fmt::Debug,
+    S: SmtSolver<E>,
+{
+    if enabled {
+        SolverRepl::new(solver, context, debug_map).run();
+    }
+}
+
+/// An `assert` command is done accumulating once its parens balance out -- which includes the
+/// trivial case of a bare, unparenthesized expression like `assert true` that never opens one
+/// to begin with. Requiring at least one open paren here would make `read_command` loop forever
+/// on exactly that case, since a line with no parens can never become "more balanced".
+fn is_balanced(text: &str) -> bool {
+    let mut depth = 0i32;
+    for ch in text.chars() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth <= 0
+}
+
+fn print_help() {
+    println!("Commands:");
+    println!("  assert <expr>             add an expression to the current context (may span multiple lines)");
+    println!("  assert-named <label> <expr>  like assert, but tagged so it can show up in unsat-core");
+    println!("  check-sat                 solve the current context and print Satisfiable/Unsatisfiable/Undefined");
+    println!("  unsat-core                explain which named assumptions are mutually contradictory");
+    println!("  model                     print the current model's variable assignments");
+    println!("  state                     print all definitions and assertions in the current context");
+    println!("  push                      open a new backtrack frame");
+    println!("  pop                       discard the current backtrack frame");
+    println!("  history                   print the commands entered this session");
+    println!("  exit                      leave the repl");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_assertion_with_no_parens_is_immediately_balanced() {
+        // The exact regression this guards: `assert true` never opens a paren, so a version
+        // of this check that required seeing one first would never terminate.
+        assert!(is_balanced("assert true"));
+        assert!(is_balanced("assert x"));
+    }
+
+    #[test]
+    fn incomplete_parenthesized_assertion_is_not_balanced() {
+        assert!(!is_balanced("assert (foo"));
+        assert!(!is_balanced("assert (and (foo) "));
+    }
+
+    #[test]
+    fn completed_multi_line_assertion_is_balanced() {
+        assert!(is_balanced("assert (and (foo)\n(bar))"));
+    }
+}
@@ -1,9 +1,17 @@
 use std::{fmt::{Debug, Formatter}, rc::Rc, collections::{HashMap, BTreeMap}};
 use log_derive::logfn_inputs;
-use rustc_middle::ty::{Ty, TyCtxt};
+use rustc_hir::def::CtorKind;
+use rustc_middle::ty::{Ty, TyCtxt, TyKind, VariantDef};
+use rustc_target::abi::VariantIdx;
 use std::io::Write;
-use crate::{smt_solver::SmtParam, abstract_value::AbstractValue, type_visitor::TypeVisitor, path::PathEnum};
-use crate::path::Path;
+use crate::{smt_solver::{SmtParam, SmtParamValue}, abstract_value::AbstractValue, type_visitor::TypeVisitor, path::PathEnum};
+use crate::path::{Path, PathSelector};
+use serde::Serialize;
+
+/// How many levels of nested struct/enum fields `synthesize_literal` will recurse through
+/// before giving up and falling back to a `todo!` stub. Guards against runaway recursion on
+/// self referential or otherwise pathological model paths.
+const MAX_SYNTHESIS_DEPTH: usize = 8;
 
 
 #[derive(Debug)]
@@ -12,6 +20,8 @@ struct ResolvedParam<'tcx>{
     pub name: Rc<String>,
     pub type_name: String,
     pub value_string: String,
+    pub value: SmtParamValue,
+    pub path: Rc<Path>,
     pub param_ordinal: Option<usize>,
     pub related_to: usize,
 }
@@ -29,6 +39,27 @@ struct FuncArg<'tcx>{
 struct Testcase<'tcx>{
     pub abstract_val: Rc<AbstractValue>,
     pub param_list: Vec<Rc<ResolvedParam<'tcx>>>,
+    pub source_span: String,
+}
+
+/// One `{path, type_name, value}` entry in a generated test's manifest record, describing a
+/// single value out of the satisfying model that MIRAI found.
+#[derive(Serialize)]
+struct ManifestParam {
+    path: String,
+    type_name: String,
+    value: SmtParamValue,
+}
+
+/// A machine readable record of one generated `#[test]`, written out next to the `.rs` file it
+/// describes so downstream tooling can map a failing test back to the counterexample MIRAI
+/// found, or tell which functions gained or lost coverage between runs.
+#[derive(Serialize)]
+struct ManifestEntry {
+    func_name_raw: String,
+    source_span: String,
+    model: Vec<ManifestParam>,
+    synthesized_args: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -136,7 +167,8 @@ impl<'tcx> TestGen<'tcx> {
             let path = param.get_path().unwrap();
             let ty = type_visitor.get_path_rustc_type(&path, current_span);
             let type_name = ty.to_string();
-            let value_string = param.get_val().to_string();
+            let value = param.get_val();
+            let value_string = value.render(&type_name);
             let param_ordinal = match path.value {
                 PathEnum::Parameter { ordinal } => Some(ordinal),
                 _ => None
@@ -151,6 +183,8 @@ impl<'tcx> TestGen<'tcx> {
                 ty,
                 type_name,
                 value_string,
+                value,
+                path: path.clone(),
                 param_ordinal,
                 related_to
             });
@@ -167,17 +201,16 @@ impl<'tcx> TestGen<'tcx> {
             resolved_params.push(resolved);
         }
 
-        func_info.testcases.push(Testcase { 
-            abstract_val: val.clone(), param_list: resolved_params 
+        func_info.testcases.push(Testcase {
+            abstract_val: val.clone(), param_list: resolved_params, source_span: format!("{:?}", current_span)
         })
 
     }
 
-    fn output_testcase(&self, test_ind: usize, func_info: &FuncTestCaseInfo<'tcx>, testcase: &Testcase<'tcx>) -> String{
-        let intializers_string: String = testcase.param_list.iter()
-            .map(|param| format!("        let {}: {} = {};\n", param.name, param.type_name, param.value_string))
-            .collect();
-
+    /// For each of `func_info`'s arguments, finds the param in `testcase` that resolves it
+    /// directly (i.e. whose path is that argument itself, not one of its fields), if any.
+    /// An arg with no entry here is the one that needs a synthesized `construct_*` call.
+    fn resolve_args<'a>(func_info: &'a FuncTestCaseInfo<'tcx>, testcase: &'a Testcase<'tcx>) -> Vec<Option<&'a Rc<ResolvedParam<'tcx>>>> {
         let mut resolved_args: Vec<Option<&Rc<ResolvedParam>>> = Vec::new();
         resolved_args.resize(func_info.args.len(), None);
         for resolved in testcase.param_list.iter(){
@@ -185,6 +218,13 @@ impl<'tcx> TestGen<'tcx> {
                 resolved_args[ord - 1] = Some(resolved);
             }
         }
+        resolved_args
+    }
+
+    fn output_testcase(&self, test_ind: usize, func_info: &FuncTestCaseInfo<'tcx>, testcase: &Testcase<'tcx>, resolved_args: &[Option<&Rc<ResolvedParam<'tcx>>>]) -> String{
+        let intializers_string: String = testcase.param_list.iter()
+            .map(|param| format!("        let {}: {} = {};\n", param.name, param.type_name, param.value_string))
+            .collect();
 
         trace!("Test ind: {}", test_ind);
         trace!("Args: {:?}", func_info.args);
@@ -235,6 +275,169 @@ impl<'tcx> TestGen<'tcx> {
         out
     }
 
+    /// Returns the chain of selectors that qualify `path` relative to the top level local it
+    /// was derived from, in root-to-leaf order (e.g. a path to `x.field0.field1` yields
+    /// `[Field(0), Field(1)]`).
+    fn selector_chain(path: &Rc<Path>) -> Vec<Rc<PathSelector>> {
+        let mut chain = Vec::new();
+        let mut current = path;
+        while let PathEnum::QualifiedPath { qualifier, selector, .. } = &current.value {
+            chain.push(selector.clone());
+            current = qualifier;
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Looks up the real field identifier for `field_index` of `variant_index` on the ADT that
+    /// `ty` names. Falls back to a positional `field{index}` name (which will not compile, but
+    /// is at least self-explanatory as a stub) if `ty` is not an ADT or the index is out of
+    /// range -- that should only happen if the model disagrees with the type layout.
+    fn field_name(ty: Ty<'tcx>, variant_index: usize, field_index: usize) -> String {
+        match ty.ty_adt_def() {
+            Some(adt_def) => adt_def.variant(VariantIdx::from_usize(variant_index)).fields.get(field_index)
+                .map(|field| field.name.to_string())
+                .unwrap_or_else(|| format!("field{}", field_index)),
+            None => format!("field{}", field_index),
+        }
+    }
+
+    /// Recursively synthesizes a Rust literal of type `ty` out of `entries`, a set of
+    /// (remaining selector chain, resolved model param) pairs all rooted at the same path.
+    /// Each resolved param contributes its value via `{name}.unwrap()`, referring to the
+    /// `Option<T>` parameter of the enclosing `construct_*` function that the model value was
+    /// threaded through. Recursion bottoms out at `MAX_SYNTHESIS_DEPTH` or at a selector this
+    /// synthesizer does not understand (e.g. `Deref`, `Index`), in either case falling back to
+    /// a `todo!` stub rather than emitting something that would not compile.
+    fn synthesize_literal(
+        &self,
+        ty: Ty<'tcx>,
+        entries: &[(Vec<Rc<PathSelector>>, &Rc<ResolvedParam<'tcx>>)],
+        depth: usize,
+    ) -> String {
+        let ty_string = ty.to_string();
+        if let Some((_, resolved)) = entries.iter().find(|(chain, _)| chain.is_empty()) {
+            return format!("{}.unwrap()", resolved.name);
+        }
+        if depth >= MAX_SYNTHESIS_DEPTH {
+            return format!("todo!(\"{} is nested too deeply to synthesize\")", ty_string);
+        }
+
+        let adt = match ty.kind() {
+            TyKind::Adt(adt_def, substs) => Some((adt_def, substs)),
+            _ => None,
+        };
+        let (adt_def, substs) = match adt {
+            Some(adt) => adt,
+            None => return format!("todo!(\"{} is not an ADT and cannot be synthesized\")", ty_string),
+        };
+
+        let variant_index: Option<usize> = entries.iter().find_map(|(chain, resolved)| {
+            match (chain.as_slice(), &resolved.value) {
+                ([selector], SmtParamValue::Discriminant { val }) if matches!(selector.as_ref(), PathSelector::Discriminant) => {
+                    Some(*val as usize)
+                }
+                _ => None,
+            }
+        });
+
+        if let Some(variant_index) = variant_index {
+            let variant = adt_def.variant(VariantIdx::from_usize(variant_index));
+            let mut fields: BTreeMap<usize, Vec<(Vec<Rc<PathSelector>>, &Rc<ResolvedParam<'tcx>>)>> = BTreeMap::new();
+            for (chain, resolved) in entries {
+                let (selector, rest) = match chain.split_first() {
+                    Some((selector, rest)) => (selector.as_ref(), rest),
+                    None => continue,
+                };
+                if let PathSelector::Downcast(_, index) = selector {
+                    if *index != variant_index {
+                        continue;
+                    }
+                    match rest.split_first() {
+                        Some((field_selector, field_rest)) => {
+                            if let PathSelector::Field(field_index) = field_selector.as_ref() {
+                                fields.entry(*field_index).or_default().push((field_rest.to_vec(), *resolved));
+                            }
+                        }
+                        None => {
+                            fields.entry(0).or_default().push((Vec::new(), *resolved));
+                        }
+                    }
+                }
+            }
+            let field_values: BTreeMap<usize, String> = fields.into_iter()
+                .map(|(index, sub_entries)| {
+                    let field_ty = variant.fields[index].ty(self.tcx, substs);
+                    (index, self.synthesize_literal(field_ty, &sub_entries, depth + 1))
+                })
+                .collect();
+            let head = format!("{}::{}", ty_string, variant.name);
+            return Self::render_adt_literal(&head, variant, field_values, |index| Self::field_name(ty, variant_index, index), false);
+        }
+
+        let variant = adt_def.non_enum_variant();
+        let mut fields: BTreeMap<usize, Vec<(Vec<Rc<PathSelector>>, &Rc<ResolvedParam<'tcx>>)>> = BTreeMap::new();
+        for (chain, resolved) in entries {
+            if let Some((selector, rest)) = chain.split_first() {
+                if let PathSelector::Field(field_index) = selector.as_ref() {
+                    fields.entry(*field_index).or_default().push((rest.to_vec(), *resolved));
+                }
+            }
+        }
+
+        if fields.is_empty() && !variant.fields.is_empty() {
+            return format!("todo!(\"could not synthesize a value for {}\")", ty_string);
+        }
+
+        let field_values: BTreeMap<usize, String> = fields.into_iter()
+            .map(|(index, sub_entries)| {
+                let field_ty = variant.fields[index].ty(self.tcx, substs);
+                (index, self.synthesize_literal(field_ty, &sub_entries, depth + 1))
+            })
+            .collect();
+        // `..Default::default()` is only legal on a named-field struct literal -- it cannot
+        // appear in a tuple-struct or unit-struct constructor at all, so `render_adt_literal`
+        // only ever splices it into the `CtorKind::None` (named-field) case.
+        Self::render_adt_literal(&ty_string, variant, field_values, |index| Self::field_name(ty, 0, index), true)
+    }
+
+    /// Renders `head` (e.g. `Option` or `Option::Some`) applied to `field_values` (keyed by
+    /// field index, in order), using whichever constructor syntax `variant`'s actual shape
+    /// supports: `head(v0, v1, ..)` for a tuple-like variant/struct, bare `head` for a
+    /// unit-like one, or `head { name: v, .. }` for a named-field one. Getting this wrong is a
+    /// hard compile error -- e.g. `Some { 0: 5 }` or `Point { 0: 1, ..Default::default() }` --
+    /// since Rust does not allow brace syntax for unnamed fields, or functional-update syntax
+    /// on a tuple struct.
+    fn render_adt_literal(
+        head: &str,
+        variant: &VariantDef,
+        field_values: BTreeMap<usize, String>,
+        field_name: impl Fn(usize) -> String,
+        allow_default_update: bool,
+    ) -> String {
+        match variant.ctor_kind() {
+            Some(CtorKind::Const) => head.to_string(),
+            Some(CtorKind::Fn) => {
+                let values: Vec<String> = field_values.into_values().collect();
+                format!("{}({})", head, values.join(", "))
+            }
+            None => {
+                let named: Vec<String> = field_values.into_iter()
+                    .map(|(index, value)| format!("{}: {}", field_name(index), value))
+                    .collect();
+                if allow_default_update {
+                    if named.is_empty() {
+                        format!("{} {{ ..Default::default() }}", head)
+                    } else {
+                        format!("{} {{ {}, ..Default::default() }}", head, named.join(", "))
+                    }
+                } else {
+                    format!("{} {{ {} }}", head, named.join(", "))
+                }
+            }
+        }
+    }
+
     fn make_constuctor_function(&self, arg: &FuncArg<'tcx>) -> String{
         let mut params: String = arg.related_to.values()
             .map(|param| format!("{}: Option<{}>, ", param.name, param.type_name))
@@ -242,38 +445,76 @@ impl<'tcx> TestGen<'tcx> {
         if params.len() > 1 {
             params.truncate(params.len() - 2);
         }
-        format!("    fn construct_{}({}) -> {}{{\n        todo!(\"Make an instance of this struct using the given params.\")\n    }}\n\n", arg.name, params, arg.ty.to_string())
+        let ty_string = arg.ty.to_string();
+        let entries: Vec<(Vec<Rc<PathSelector>>, &Rc<ResolvedParam<'tcx>>)> = arg.related_to.values()
+            .map(|param| (Self::selector_chain(&param.path), param))
+            .collect();
+        let body = self.synthesize_literal(arg.ty, &entries, 0);
+        format!("    fn construct_{}({}) -> {}{{\n        {}\n    }}\n\n", arg.name, params, ty_string, body)
+    }
+
+    /// Builds the manifest record for one generated `#[test]`, recording the satisfying model
+    /// and which arguments needed a synthesized `construct_*` call.
+    fn manifest_entry(func_info: &FuncTestCaseInfo<'tcx>, testcase: &Testcase<'tcx>, resolved_args: &[Option<&Rc<ResolvedParam<'tcx>>>]) -> ManifestEntry {
+        let synthesized_args = func_info.args.iter().enumerate()
+            .filter(|(i, _)| resolved_args[*i].is_none())
+            .map(|(_, arg)| arg.name.to_string())
+            .collect();
+
+        let model = testcase.param_list.iter()
+            .map(|param| ManifestParam {
+                path: format!("{:?}", param.path),
+                type_name: param.type_name.clone(),
+                value: param.value.clone(),
+            })
+            .collect();
+
+        ManifestEntry {
+            func_name_raw: func_info.func_name_raw.to_string(),
+            source_span: testcase.source_span.clone(),
+            model,
+            synthesized_args,
+        }
     }
 
-    fn output_function_testcases(&self, func_info: &FuncTestCaseInfo<'tcx>) -> String{
+    fn output_function_testcases(&self, func_info: &FuncTestCaseInfo<'tcx>) -> (String, Vec<ManifestEntry>){
         let constructor_functions: String = func_info.args.iter()
             .map(|arg| self.make_constuctor_function(arg))
             .collect();
         let mut out = format!("\n#[cfg(test)]\nmod {}_tests {{\n    use super::*;\n\n{}", &func_info.func_name, constructor_functions);
 
         trace!("Output tests for {}", func_info.func_name);
+        let mut manifest = Vec::new();
         for (test_ind, testcase) in func_info.testcases.iter().enumerate(){
-            let testcase_str = self.output_testcase(test_ind, func_info, testcase);
+            let resolved_args = Self::resolve_args(func_info, testcase);
+            let testcase_str = self.output_testcase(test_ind, func_info, testcase, &resolved_args);
             out.push_str(&testcase_str.to_string());
+            manifest.push(Self::manifest_entry(func_info, testcase, &resolved_args));
         }
 
         out.push_str("}\n");
-        out
+        (out, manifest)
     }
 
     fn output_internal(&self) -> Result<(), Box<dyn std::error::Error>>{
 
         std::fs::create_dir_all(&self.test_output_dir)?;
-        
+
         for (func_name, func_info) in self.testcase_map.iter(){
-            let function_str = self.output_function_testcases(func_info);
+            let (function_str, manifest) = self.output_function_testcases(func_info);
 
             let mut file = std::fs::File::create(format!("{}/{}_tests.rs", self.test_output_dir, func_name))?;
 
             write!(file, "{}", function_str)?;
-        }   
-    
-        Ok(())   
+
+            let manifest_str = serde_json::to_string_pretty(&manifest)?;
+
+            let mut manifest_file = std::fs::File::create(format!("{}/{}_tests.manifest.json", self.test_output_dir, func_name))?;
+
+            write!(manifest_file, "{}", manifest_str)?;
+        }
+
+        Ok(())
     }
 
     #[logfn_inputs(TRACE)]
@@ -285,4 +526,35 @@ impl<'tcx> TestGen<'tcx> {
             Err(e) => {error!("test_gen output error: {}", e)}
         };
     }
+}
+
+// `synthesize_literal`/`render_adt_literal`'s recursion over tuple- vs struct- vs unit-shaped
+// ADTs is the part of this module most worth unit testing directly, but doing so needs a real
+// `Ty<'tcx>`/`VariantDef` -- both of which only exist inside a live `TyCtxt`, which in turn
+// only exists inside a running rustc session (there is no way to hand-construct one in an
+// ordinary `#[test]`). What *is* plain data, and so testable here, is the manifest format that
+// `output_function_testcases` emits.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_entry_serializes_the_documented_shape() {
+        let entry = ManifestEntry {
+            func_name_raw: "foo".to_string(),
+            source_span: "src/lib.rs:1:1".to_string(),
+            model: vec![ManifestParam {
+                path: "p0".to_string(),
+                type_name: "i32".to_string(),
+                value: SmtParamValue::Numeral { val: 5 },
+            }],
+            synthesized_args: vec!["arg1".to_string()],
+        };
+        let json: serde_json::Value = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["func_name_raw"], "foo");
+        assert_eq!(json["source_span"], "src/lib.rs:1:1");
+        assert_eq!(json["model"][0]["path"], "p0");
+        assert_eq!(json["model"][0]["type_name"], "i32");
+        assert_eq!(json["synthesized_args"][0], "arg1");
+    }
 }
\ No newline at end of file